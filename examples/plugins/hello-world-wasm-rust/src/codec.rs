@@ -0,0 +1,68 @@
+//! Wire codec negotiation for host<->plugin payloads.
+//!
+//! Every exported hook pays a `serde_json::from_str`/`to_string` round-trip
+//! per call, which shows up on the profile for large streaming chunk
+//! payloads. This module lets the host ask the plugin which codec it
+//! supports (`get_codec`) and, when the `msgpack` feature is enabled,
+//! encode/decode hook payloads as MessagePack instead of JSON.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Wire formats a plugin can speak to the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Json,
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl Codec {
+    /// Name reported to the host via `get_codec`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Codec::Json => "json",
+            #[cfg(feature = "msgpack")]
+            Codec::MsgPack => "msgpack",
+        }
+    }
+}
+
+/// The codec this build of the plugin negotiates with the host.
+/// MessagePack is preferred when compiled in, since it avoids the text
+/// round-trip entirely; otherwise the plugin falls back to JSON.
+pub fn active_codec() -> Codec {
+    #[cfg(feature = "msgpack")]
+    {
+        Codec::MsgPack
+    }
+    #[cfg(not(feature = "msgpack"))]
+    {
+        Codec::Json
+    }
+}
+
+/// Decode a hook input using the active codec.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    match active_codec() {
+        Codec::Json => {
+            serde_json::from_slice(bytes).map_err(|e| format!("json decode error: {}", e))
+        }
+        #[cfg(feature = "msgpack")]
+        Codec::MsgPack => {
+            rmp_serde::from_slice(bytes).map_err(|e| format!("msgpack decode error: {}", e))
+        }
+    }
+}
+
+/// Encode a hook output using the active codec.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    match active_codec() {
+        Codec::Json => {
+            serde_json::to_vec(value).map_err(|e| format!("json encode error: {}", e))
+        }
+        #[cfg(feature = "msgpack")]
+        Codec::MsgPack => {
+            rmp_serde::to_vec_named(value).map_err(|e| format!("msgpack encode error: {}", e))
+        }
+    }
+}
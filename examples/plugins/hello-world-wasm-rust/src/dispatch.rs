@@ -0,0 +1,138 @@
+//! Unified decode/dispatch/encode pipeline for exported hooks.
+//!
+//! Every hook used to repeat the same boilerplate: decode the input,
+//! build a differently-shaped error output by hand on failure (and, in
+//! `http_intercept`'s case only, inject debug context around the error
+//! position), call the handler, then encode the result. `run_hook`
+//! centralizes all of that behind one generic entry point, so each hook's
+//! `#[no_mangle]` wrapper shrinks to just its actual logic.
+
+use crate::codec;
+use crate::memory::{read_bytes, reset_arena, write_bytes};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+
+/// Error produced while running a hook's handler.
+#[derive(Debug)]
+pub struct PluginError(String);
+
+impl PluginError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Lets `run_hook` write an error message into whichever field an output
+/// type uses for it (`error: String`, `hook_error: String`, ...), without
+/// every hook needing its own error-construction arm.
+pub trait HasErrorField: Default {
+    fn with_error(message: String) -> Self;
+}
+
+/// Decode `input_ptr`/`input_len` in the negotiated codec, run `f`, and
+/// encode the result - or an error output built via `HasErrorField` on
+/// decode/handler failure - back into arena memory.
+pub fn run_hook<I, O, F>(input_ptr: u32, input_len: u32, f: F) -> u64
+where
+    I: DeserializeOwned,
+    O: Serialize + HasErrorField,
+    F: FnOnce(I) -> Result<O, PluginError>,
+{
+    reset_arena();
+    let input_bytes = read_bytes(input_ptr, input_len);
+
+    let input: I = match codec::decode(&input_bytes) {
+        Ok(i) => i,
+        Err(e) => {
+            let message = format!("Failed to parse input: {}{}", e, debug_context(&input_bytes, &e));
+            crate::warn!("run_hook: {}", message);
+            let output = O::with_error(message);
+            return write_bytes(&codec::encode(&output).unwrap_or_default());
+        }
+    };
+
+    let output = match f(input) {
+        Ok(o) => o,
+        Err(e) => {
+            crate::warn!("run_hook: handler error: {}", e);
+            O::with_error(e.to_string())
+        }
+    };
+
+    write_bytes(&codec::encode(&output).unwrap_or_default())
+}
+
+/// Include context around a JSON parse error's position, for debugging.
+/// Only JSON errors carry a "line L column C" we can map back to source
+/// text.
+fn debug_context(input_bytes: &[u8], err: &str) -> String {
+    if codec::active_codec() != codec::Codec::Json {
+        return String::new();
+    }
+    match extract_line_col(err) {
+        Some((line, col)) => {
+            let input_str = String::from_utf8_lossy(input_bytes);
+            let char_offset = char_offset_for_line_col(&input_str, line, col);
+            let char_start = char_offset.saturating_sub(50);
+            let char_end = char_offset + 50;
+            let start = char_to_byte_index(&input_str, char_start);
+            let end = char_to_byte_index(&input_str, char_end);
+            format!(" | context: ...{}...", &input_str[start..end])
+        }
+        None => String::new(),
+    }
+}
+
+/// Resolve serde_json's 1-based `(line, column)` to a 0-based character
+/// offset into `s`, so the context window can be taken around the actual
+/// error position rather than the column number alone (which is only
+/// meaningful relative to its own line).
+fn char_offset_for_line_col(s: &str, line: usize, col: usize) -> usize {
+    let mut offset = 0;
+    let mut lines = s.split('\n');
+    for _ in 1..line {
+        match lines.next() {
+            Some(l) => offset += l.chars().count() + 1, // +1 for the newline
+            None => return s.chars().count(),
+        }
+    }
+    offset + col.saturating_sub(1)
+}
+
+/// Map a character offset to the byte offset of that char boundary,
+/// clamping to the end of the string. Used instead of indexing by raw
+/// column number, which can land inside a multibyte UTF-8 sequence and
+/// panic.
+fn char_to_byte_index(s: &str, char_offset: usize) -> usize {
+    s.char_indices()
+        .nth(char_offset)
+        .map(|(byte_offset, _)| byte_offset)
+        .unwrap_or(s.len())
+}
+
+/// Extract `(line, column)` from a serde error message like
+/// "... at line 1 column 5" for debugging.
+fn extract_line_col(error_msg: &str) -> Option<(usize, usize)> {
+    let col_idx = error_msg.rfind("column ")?;
+    let col: usize = error_msg[col_idx + 7..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+
+    let line_idx = error_msg.rfind("line ")?;
+    let line: usize = error_msg[line_idx + 5..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+
+    Some((line, col))
+}
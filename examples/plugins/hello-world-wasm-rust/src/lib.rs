@@ -5,9 +5,23 @@
 //!
 //! Build with: cargo build --release --target wasm32-unknown-unknown
 
+mod codec;
+mod dispatch;
+#[macro_use]
+mod log;
 mod memory;
+mod payload;
+mod query;
+mod response;
+#[cfg(feature = "schema")]
+mod schema;
+mod state;
+mod stream;
+mod telemetry;
 mod types;
+mod validation;
 
+use dispatch::run_hook;
 use memory::{read_string, write_string};
 use types::*;
 
@@ -24,12 +38,19 @@ pub extern "C" fn get_name() -> u64 {
     write_string("hello-world-wasm-rust")
 }
 
+/// Report the wire codec this plugin speaks for hook payloads ("json" or
+/// "msgpack"), so the host can pick a matching format.
+#[no_mangle]
+pub extern "C" fn get_codec() -> u64 {
+    write_string(codec::active_codec().as_str())
+}
+
 /// Initialize the plugin with config
 /// Returns 0 on success, non-zero on error
 #[no_mangle]
 pub extern "C" fn init(config_ptr: u32, config_len: u32) -> i32 {
     let config_str = read_string(config_ptr, config_len);
-    
+
     // Parse configuration
     let config: PluginConfig = if config_str.is_empty() {
         PluginConfig::default()
@@ -39,12 +60,14 @@ pub extern "C" fn init(config_ptr: u32, config_len: u32) -> i32 {
             Err(_) => return 1, // Config parse error
         }
     };
-    
+
+    info!("init: parsed config {:?}", config);
+
     // Store configuration
     unsafe {
         PLUGIN_CONFIG = Some(config);
     }
-    
+
     0 // Success
 }
 
@@ -53,46 +76,47 @@ pub extern "C" fn init(config_ptr: u32, config_len: u32) -> i32 {
 /// Can modify headers, query params, or short-circuit with a response.
 #[no_mangle]
 pub extern "C" fn http_intercept(input_ptr: u32, input_len: u32) -> u64 {
-    let input_str = read_string(input_ptr, input_len);
-    
-    // Parse input
-    let input: HTTPInterceptInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            // Include context around the error position for debugging
-            let error_context = if let Some(col) = extract_column(&e.to_string()) {
-                let start = col.saturating_sub(50);
-                let end = (col + 50).min(input_str.len());
-                format!(" | context: ...{}...", &input_str[start..end])
-            } else {
-                String::new()
-            };
-            let output = HTTPInterceptOutput {
-                error: format!("Failed to parse input: {}{}", e, error_context),
+    run_hook(input_ptr, input_len, |input: HTTPInterceptInput| {
+        // Add context value like Go plugin does
+        let mut context = input.context;
+        context.set_value("from-http", serde_json::json!("123"));
+
+        // Example: reject requests whose content-type/body don't match
+        // what the route expects. Uncomment to test:
+        /*
+        let expected = match input.request.path.as_str() {
+            "/health" => validation::RequestValidation::none(),
+            "/v1/chat/completions" => validation::RequestValidation::content_type("application/json"),
+            _ => return Ok(HTTPInterceptOutput {
+                context,
+                request: Some(serde_json::to_value(&input.request).unwrap_or_default()),
+                has_response: false,
                 ..Default::default()
-            };
-            return write_string(&serde_json::to_string(&output).unwrap_or_default());
+            }),
+        };
+        if let Err(error) = expected.validate(&input.request) {
+            return Ok(HTTPInterceptOutput {
+                context,
+                has_response: true,
+                response: Some(
+                    HTTPResponse::builder()
+                        .status(error.status_code.unwrap_or(400) as u16)
+                        .body(serde_json::json!({ "error": error.error.message }))
+                        .build(),
+                ),
+                ..Default::default()
+            });
         }
-    };
-    
-
-    // Add context value like Go plugin does
-    let mut context = input.context;
-    context.set_value("from-http", serde_json::json!("123"));
-    
-    // Create output with context and request preserved (pass-through)
-    // Serialize request to Value to ensure proper JSON structure
-    let request_value = serde_json::to_value(&input.request).ok();
-    
-    let output = HTTPInterceptOutput {
-        context: input.context,
-        request: input.request,
-        has_response: false,
-        ..Default::default()
-    };
-    
-    // Pass through
-    write_string(&serde_json::to_string(&output).unwrap_or_default())
+        */
+
+        // Pass through
+        Ok(HTTPInterceptOutput {
+            context,
+            request: Some(serde_json::to_value(&input.request).unwrap_or_default()),
+            has_response: false,
+            ..Default::default()
+        })
+    })
 }
 
 /// Pre-request hook
@@ -100,96 +124,85 @@ pub extern "C" fn http_intercept(input_ptr: u32, input_len: u32) -> u64 {
 /// Can modify the request or short-circuit with a response/error.
 #[no_mangle]
 pub extern "C" fn pre_hook(input_ptr: u32, input_len: u32) -> u64 {
-    let input_str = read_string(input_ptr, input_len);
-    
-    // Parse input
-    let input: PreHookInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let output = PreHookOutput {
-                error: format!("Failed to parse input: {}", e),
-                ..Default::default()
-            };
-            return write_string(&serde_json::to_string(&output).unwrap_or_default());
-        }
-    };
-    
-    // Create output with context preserved
-    let mut output = PreHookOutput {
-        context: input.context.clone(),
-        request: input.request.clone(),
-        has_short_circuit: false,
-        ..Default::default()
-    };
-    
-    // Get provider and model for potential modifications
-    let (_provider, model) = input.get_provider_model();
-    
-    // Example: Short-circuit with mock response for specific model
-    // Uncomment to test:
-    /*
-    if model == "mock-model" {
-        output.has_short_circuit = true;
-        
-        let mock_response = BifrostResponse {
-            chat_response: Some(BifrostChatResponse {
-                id: format!("mock-{}", input.context.request_id.unwrap_or_default()),
-                model: "mock-model".to_string(),
-                choices: vec![ResponseChoice {
-                    index: 0,
-                    message: Some(ChatMessage {
-                        role: ChatMessageRole::Assistant,
-                        content: Some(ChatMessageContent::Text(
-                            "This is a mock response from the Rust WASM plugin!".to_string()
-                        )),
+    run_hook(input_ptr, input_len, |input: PreHookInput| {
+        // Create output with context preserved
+        let mut output = PreHookOutput {
+            context: input.context.clone(),
+            request: input.request.clone(),
+            has_short_circuit: false,
+            ..Default::default()
+        };
+
+        // Get provider and model for potential modifications
+        let (_provider, model) = input.get_provider_model();
+
+        // Example: Short-circuit with mock response for specific model
+        // Uncomment to test:
+        /*
+        if model == "mock-model" {
+            output.has_short_circuit = true;
+
+            let mock_response = BifrostResponse {
+                chat_response: Some(BifrostChatResponse {
+                    id: format!("mock-{}", input.context.request_id.unwrap_or_default()),
+                    model: "mock-model".to_string(),
+                    choices: vec![ResponseChoice {
+                        index: 0,
+                        message: Some(ChatMessage {
+                            role: ChatMessageRole::Assistant,
+                            content: Some(ChatMessageContent::Text(
+                                "This is a mock response from the Rust WASM plugin!".to_string()
+                            )),
+                            ..Default::default()
+                        }),
+                        finish_reason: Some("stop".to_string()),
+                        ..Default::default()
+                    }],
+                    usage: Some(LLMUsage {
+                        prompt_tokens: 10,
+                        completion_tokens: 15,
+                        total_tokens: 25,
                         ..Default::default()
                     }),
-                    finish_reason: Some("stop".to_string()),
-                    ..Default::default()
-                }],
-                usage: Some(LLMUsage {
-                    prompt_tokens: 10,
-                    completion_tokens: 15,
-                    total_tokens: 25,
                     ..Default::default()
                 }),
                 ..Default::default()
-            }),
-            ..Default::default()
-        };
-        
-        output.short_circuit = Some(PluginShortCircuit {
-            response: Some(mock_response),
-            error: None,
-        });
-        
-        return write_string(&serde_json::to_string(&output).unwrap_or_default());
-    }
-    */
-    
-    // Example: Short-circuit with rate limit error
-    // Uncomment to test:
-    /*
-    if should_rate_limit(&input.context) {
-        output.has_short_circuit = true;
-        output.short_circuit = Some(PluginShortCircuit {
-            response: None,
-            error: Some(
-                BifrostError::new("Rate limit exceeded")
-                    .with_type("rate_limit")
-                    .with_code("429")
-                    .with_status(429)
-            ),
-        });
-        return write_string(&serde_json::to_string(&output).unwrap_or_default());
-    }
-    */
+            };
+
+            output.short_circuit = Some(PluginShortCircuit {
+                response: Some(mock_response),
+                error: None,
+            });
+
+            return Ok(output);
+        }
+        */
+
+        // Example: Short-circuit with rate limit error
+        // Uncomment to test:
+        /*
+        let rate_limit_key = input.context.get_string("request_id").unwrap_or(&model);
+        if state::should_rate_limit(rate_limit_key, &state::RateLimitConfig::default()) {
+            output.has_short_circuit = true;
+            output.short_circuit = Some(PluginShortCircuit {
+                response: None,
+                error: Some(
+                    BifrostError::new("Rate limit exceeded")
+                        .with_type("rate_limit")
+                        .with_code("429")
+                        .with_status(429)
+                ),
+            });
+            return Ok(output);
+        }
+        */
 
-    // Silence unused variable warning in example code
-    let _ = model;
-    
-    // Pass through - empty request means use original
-    write_string(&serde_json::to_string(&output).unwrap_or_default())
+        // Silence unused variable warning in example code
+        let _ = model;
+
+        // Pass through - empty request means use original
+        Ok(output)
+    })
 }
 
 /// Post-response hook
@@ -197,63 +210,75 @@ pub extern "C" fn pre_hook(input_ptr: u32, input_len: u32) -> u64 {
 /// Can modify the response or error.
 #[no_mangle]
 pub extern "C" fn post_hook(input_ptr: u32, input_len: u32) -> u64 {
-    let input_str = read_string(input_ptr, input_len);
-    
-    // Parse input
-    let input: PostHookInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let output = PostHookOutput {
-                hook_error: format!("Failed to parse input: {}", e),
-                ..Default::default()
-            };
-            return write_string(&serde_json::to_string(&output).unwrap_or_default());
+    run_hook(input_ptr, input_len, |input: PostHookInput| {
+        // Add context value like Go plugin does
+        let mut context = input.context.clone();
+        context.set_value("from-post-hook", serde_json::json!("456"));
+
+        // Create output with context and response/error preserved (pass-through)
+        // This matches Go plugin behavior exactly
+        let output = PostHookOutput {
+            context,
+            response: Some(input.response.clone()),
+            error: Some(input.error.clone()),
+            has_error: input.has_error,
+            hook_error: String::new(),
+        };
+
+        // Example: Modify error message when has_error is true
+        // Uncomment to test:
+        /*
+        if input.has_error {
+            if let Some(mut error) = input.parse_error() {
+                error.error.message = format!("{} (processed by Rust WASM plugin)", error.error.message);
+                let mut output = output;
+                output.error = Some(serde_json::to_value(&error).unwrap_or_default());
+                return Ok(output);
+            }
         }
-    };
-    
-    // Add context value like Go plugin does
-    let mut context = input.context.clone();
-    context.set_value("from-post-hook", serde_json::json!("456"));
-    
-    // Create output with context and response/error preserved (pass-through)
-    // This matches Go plugin behavior exactly
-    let output = PostHookOutput {
-        context,
-        response: Some(input.response.clone()),
-        error: Some(input.error.clone()),
-        has_error: input.has_error,
-        hook_error: String::new(),
-    };
-    
-    // Example: Modify error message when has_error is true
-    // Uncomment to test:
-    /*
-    if input.has_error {
-        if let Some(mut error) = input.parse_error() {
-            error.error.message = format!("{} (processed by Rust WASM plugin)", error.error.message);
+        */
+
+        // Example: Modify response
+        // Uncomment to test:
+        /*
+        if let Some(mut response) = input.parse_response() {
+            // Add custom metadata, modify model name, etc.
+            if let Some(ref mut chat) = response.chat_response {
+                // Add a marker to the model name
+                chat.model = format!("{} (via rust-wasm)", chat.model);
+            }
             let mut output = output;
-            output.error = Some(serde_json::to_value(&error).unwrap_or_default());
-            return write_string(&serde_json::to_string(&output).unwrap_or_default());
+            output.response = Some(serde_json::to_value(&response).unwrap_or_default());
+            return Ok(output);
         }
-    }
-    */
-    
-    // Example: Modify response
-    // Uncomment to test:
-    /*
-    if let Some(mut response) = input.parse_response() {
-        // Add custom metadata, modify model name, etc.
-        if let Some(ref mut chat) = response.chat_response {
-            // Add a marker to the model name
-            chat.model = format!("{} (via rust-wasm)", chat.model);
+        */
+
+        // Example: Record token usage for this request, plus a post_hook
+        // transaction entry (started here, so its duration is near zero).
+        // Uncomment to test:
+        /*
+        if let Some(response) = input.parse_response() {
+            let request_id = context.get_string("request_id").unwrap_or_default().to_string();
+            let mut envelope = telemetry::TelemetryEnvelope::new(&request_id);
+            envelope.record_transaction(telemetry::Transaction::new(
+                "post_hook",
+                state::StateStore::now_millis(),
+            ));
+            if let Some(chat) = &response.chat_response {
+                envelope.record_metrics(telemetry::Metrics {
+                    provider: String::new(),
+                    model: chat.model.clone(),
+                    usage: chat.usage.clone(),
+                });
+            }
+            let mut output = output;
+            output.attach_telemetry(&envelope);
+            return Ok(output);
         }
-        let mut output = output;
-        output.response = Some(serde_json::to_value(&response).unwrap_or_default());
-        return write_string(&serde_json::to_string(&output).unwrap_or_default());
-    }
-    */
-    
-    write_string(&serde_json::to_string(&output).unwrap_or_default())
+        */
+
+        Ok(output)
+    })
 }
 
 /// HTTP stream chunk hook
@@ -261,34 +286,87 @@ pub extern "C" fn post_hook(input_ptr: u32, input_len: u32) -> u64 {
 /// Can modify, skip, or stop streaming based on return values.
 #[no_mangle]
 pub extern "C" fn http_stream_chunk_hook(input_ptr: u32, input_len: u32) -> u64 {
-    let input_str = read_string(input_ptr, input_len);
-    
-    // Parse input
-    let input: HTTPStreamChunkHookInput = match serde_json::from_str(&input_str) {
-        Ok(i) => i,
-        Err(e) => {
-            let output = HTTPStreamChunkHookOutput {
-                error: format!("Failed to parse input: {}", e),
-                ..Default::default()
-            };
-            return write_string(&serde_json::to_string(&output).unwrap_or_default());
+    run_hook(input_ptr, input_len, |input: HTTPStreamChunkHookInput| {
+        // Add context value like Go plugin does
+        let mut context = input.context.clone();
+        context.set_value("from-stream-chunk", serde_json::json!("rust-wasm"));
+
+        // Example: redact a marker that may be split across chunk
+        // boundaries, using a per-stream buffer that survives across
+        // calls. Uncomment to test:
+        /*
+        const MARKER: &str = "SECRET-TOKEN";
+        let request_id = context.get_string("request_id").unwrap_or_default().to_string();
+        let mut buffer = stream::StreamBuffer::load(&request_id);
+        let text = input.chunk.get("content").and_then(|v| v.as_str()).unwrap_or_default();
+        buffer.append(text);
+
+        if let Some(pos) = buffer.tail().find(MARKER) {
+            let redacted = buffer.tail().replacen(MARKER, "[REDACTED]", 1);
+            buffer.hold(""); // whole tail consumed into this emission
+            return Ok(HTTPStreamChunkHookOutput {
+                context,
+                chunk: Some(serde_json::json!({ "content": redacted })),
+                has_chunk: true,
+                skip: false,
+                buffered: false,
+                error: String::new(),
+            });
+        } else if MARKER.starts_with(buffer.tail()) && !buffer.tail().is_empty() {
+            // Might still complete the marker on the next chunk - hold it.
+            let tail = buffer.tail().to_string();
+            buffer.hold(&tail);
+            return Ok(HTTPStreamChunkHookOutput {
+                context,
+                chunk: None,
+                has_chunk: false,
+                skip: false,
+                buffered: true,
+                error: String::new(),
+            });
+        } else if buffer.tail().len() > text.len() {
+            // Text held back from earlier chunks: release it with this one.
+            let tail = buffer.tail().to_string();
+            buffer.flush();
+            return Ok(HTTPStreamChunkHookOutput {
+                context,
+                chunk: Some(serde_json::json!({ "content": tail })),
+                has_chunk: true,
+                skip: false,
+                buffered: false,
+                error: String::new(),
+            });
+        } else {
+            buffer.flush();
         }
-    };
-    
-    // Add context value like Go plugin does
-    let mut context = input.context.clone();
-    context.set_value("from-stream-chunk", serde_json::json!("rust-wasm"));
-    
-    // Pass through chunk unchanged
-    let output = HTTPStreamChunkHookOutput {
-        context,
-        chunk: Some(input.chunk),
-        has_chunk: true,
-        skip: false,
-        error: String::new(),
-    };
-    
-    write_string(&serde_json::to_string(&output).unwrap_or_default())
+        */
+
+        // Example: reassemble the full response across chunks to make a
+        // decision once it's complete (e.g. scanning final content for
+        // policy violations). Uncomment to test:
+        /*
+        let request_id = context.get_string("request_id").unwrap_or_default().to_string();
+        let key = format!("stream_accumulator:{}", request_id);
+        let mut accumulator: stream::StreamAccumulator = state::StateStore::get_json(&key).unwrap_or_default();
+        accumulator.accumulate(&input.chunk);
+        if accumulator.is_complete() {
+            let _full_response = accumulator.snapshot();
+            state::StateStore::set(&key, "");
+        } else {
+            state::StateStore::set_json(&key, &accumulator);
+        }
+        */
+
+        // Pass through chunk unchanged
+        Ok(HTTPStreamChunkHookOutput {
+            context,
+            chunk: Some(input.chunk),
+            has_chunk: true,
+            skip: false,
+            buffered: false,
+            error: String::new(),
+        })
+    })
 }
 
 /// Cleanup resources
@@ -296,32 +374,12 @@ pub extern "C" fn http_stream_chunk_hook(input_ptr: u32, input_len: u32) -> u64
 /// Returns 0 on success, non-zero on error
 #[no_mangle]
 pub extern "C" fn cleanup() -> i32 {
+    info!("cleanup: unloading plugin");
+
     // Clear stored configuration
     unsafe {
         PLUGIN_CONFIG = None;
     }
-    
-    0 // Success
-}
-
-// =============================================================================
-// Helper Functions
-// =============================================================================
-
-/// Extract column number from serde error message for debugging
-fn extract_column(error_msg: &str) -> Option<usize> {
-    // Error format: "... at line X column Y"
-    if let Some(idx) = error_msg.rfind("column ") {
-        let col_str = &error_msg[idx + 7..];
-        col_str.split_whitespace().next()?.parse().ok()
-    } else {
-        None
-    }
-}
 
-/// Example rate limit check function
-#[allow(dead_code)]
-fn should_rate_limit(_context: &BifrostContext) -> bool {
-    // Implement your rate limiting logic here
-    false
+    0 // Success
 }
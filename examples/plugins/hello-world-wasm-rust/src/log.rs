@@ -0,0 +1,58 @@
+//! Structured host logging for WASM plugins.
+//!
+//! Plugins otherwise have no way to report anything except by stuffing
+//! strings into a hook's `error` output field, so diagnostics that don't
+//! represent a hook failure (e.g. a parsed config, a non-fatal parse
+//! warning) are silently dropped. This module declares a `host_log` host
+//! import and wraps it in `info!`/`warn!`/`error!` macros so plugin-side
+//! diagnostics show up in Bifrost's own log stream.
+
+use crate::memory::write_string;
+
+extern "C" {
+    /// Emit a log line to the host. `level` follows `LogLevel`'s
+    /// discriminant, `msg_ptr`/`msg_len` point at a string written via
+    /// `write_string`.
+    fn host_log(level: u32, msg_ptr: u32, msg_len: u32);
+}
+
+/// Severity of a logged message, mirroring common log-level conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum LogLevel {
+    Info = 0,
+    Warn = 1,
+    Error = 2,
+}
+
+/// Send `msg` to the host log stream at the given `level`.
+pub fn log(level: LogLevel, msg: &str) {
+    let packed = write_string(msg);
+    let ptr = (packed >> 32) as u32;
+    let len = packed as u32;
+    unsafe { host_log(level as u32, ptr, len) };
+}
+
+/// Log an informational message, formatted like `format!`.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Info, &format!($($arg)*))
+    };
+}
+
+/// Log a warning message, formatted like `format!`.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Warn, &format!($($arg)*))
+    };
+}
+
+/// Log an error message, formatted like `format!`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::log($crate::log::LogLevel::Error, &format!($($arg)*))
+    };
+}
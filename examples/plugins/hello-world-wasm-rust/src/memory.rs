@@ -1,42 +1,163 @@
 //! Memory management utilities for WASM plugins.
 //! Handles allocation, deallocation, and string read/write operations.
+//!
+//! Per-call scratch (the buffers `write_string`/`write_bytes` hand back to
+//! the host) is served from a bump arena instead of the system allocator:
+//! `malloc`/`free`'s `Layout::from_size_align(size, 1)` fragments badly
+//! under the many small allocations a single hook invocation makes, and a
+//! host that forgets to call `free` on any one of them leaks for the
+//! lifetime of the instance. The arena hands out offsets into a growable
+//! region and is rewound wholesale by `reset_arena`, called at the top of
+//! every exported hook, so per-call allocations are reclaimed in one shot
+//! without the host freeing them individually (`free` only accepts
+//! pointers returned by `malloc`, never arena pointers).
+//!
+//! `malloc`/`free` keep working unchanged for host-owned buffers (e.g. the
+//! input payload the host writes before calling a hook), routed to the
+//! system allocator so their lifetime isn't tied to the arena's reset.
 
 use std::alloc::{alloc, dealloc, Layout};
 use std::slice;
 
+/// Size of each growth chunk the arena allocates from the system allocator.
+const ARENA_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A single growable region the arena bump-allocates from.
+struct Chunk {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+/// Bump allocator for per-invocation scratch memory.
+struct Arena {
+    chunks: Vec<Chunk>,
+    offset: usize,
+}
+
+impl Arena {
+    const fn new() -> Self {
+        Arena {
+            chunks: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Hand out `size` bytes, growing the arena if the current chunk can't
+    /// satisfy the request.
+    fn alloc(&mut self, size: usize) -> *mut u8 {
+        if size == 0 {
+            return std::ptr::null_mut();
+        }
+
+        let needs_new_chunk = match self.chunks.last() {
+            Some(chunk) => self.offset + size > chunk.layout.size(),
+            None => true,
+        };
+
+        if needs_new_chunk {
+            let chunk_size = size.max(ARENA_CHUNK_SIZE);
+            let layout = match Layout::from_size_align(chunk_size, 8) {
+                Ok(l) => l,
+                Err(_) => return std::ptr::null_mut(),
+            };
+            let ptr = unsafe { alloc(layout) };
+            if ptr.is_null() {
+                return std::ptr::null_mut();
+            }
+            self.chunks.push(Chunk { ptr, layout });
+            self.offset = 0;
+        }
+
+        let chunk = self.chunks.last().expect("chunk just pushed or present");
+        let out = unsafe { chunk.ptr.add(self.offset) };
+        self.offset += size;
+        out
+    }
+
+    /// Rewind the bump pointer, reclaiming every allocation made since the
+    /// last reset. Keeps the most recently used chunk around (it's likely
+    /// to be reused next call) and frees the rest.
+    fn reset(&mut self) {
+        if self.chunks.len() > 1 {
+            let keep = self.chunks.pop();
+            for chunk in self.chunks.drain(..) {
+                unsafe { dealloc(chunk.ptr, chunk.layout) };
+            }
+            if let Some(chunk) = keep {
+                self.chunks.push(chunk);
+            }
+        }
+        self.offset = 0;
+    }
+}
+
+// WASM is single-threaded, so a plain `static mut` is the same pattern
+// already used for `PLUGIN_CONFIG` in lib.rs. Unlike `PLUGIN_CONFIG`'s bare
+// place-assignment, `Arena::reset`/`Arena::alloc` need `&mut Arena`, so
+// access goes through `addr_of_mut!` rather than `&mut ARENA` directly -
+// the latter trips the `static_mut_refs` lint (hard error on edition 2024).
+static mut ARENA: Arena = Arena::new();
+
+/// Rewind the per-invocation arena, reclaiming all scratch allocations
+/// made since the last reset. Called at the top of every exported hook.
+#[no_mangle]
+pub extern "C" fn reset_arena() {
+    unsafe { (*std::ptr::addr_of_mut!(ARENA)).reset() };
+}
+
 /// Pack a pointer and length into a single u64
 /// Upper 32 bits: pointer, Lower 32 bits: length
 pub fn pack_result(ptr: u32, len: u32) -> u64 {
     ((ptr as u64) << 32) | (len as u64)
 }
 
-/// Write a string to WASM memory and return packed pointer+length
+/// Write a string to WASM memory and return packed pointer+length.
+/// Served from the per-invocation arena; no `free` call is required.
 pub fn write_string(s: &str) -> u64 {
-    if s.is_empty() {
+    write_bytes(s.as_bytes())
+}
+
+/// Read a string from WASM memory given pointer and length
+pub fn read_string(ptr: u32, len: u32) -> String {
+    if len == 0 {
+        return String::new();
+    }
+    let bytes = unsafe { slice::from_raw_parts(ptr as *const u8, len as usize) };
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Write raw bytes to WASM memory and return packed pointer+length.
+/// Served from the per-invocation arena; no `free` call is required.
+pub fn write_bytes(bytes: &[u8]) -> u64 {
+    if bytes.is_empty() {
         return 0;
     }
-    let bytes = s.as_bytes();
-    let ptr = unsafe { malloc(bytes.len() as u32) };
-    if ptr == 0 {
+    let ptr = unsafe { (*std::ptr::addr_of_mut!(ARENA)).alloc(bytes.len()) };
+    if ptr.is_null() {
         return 0;
     }
     unsafe {
-        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
     }
-    pack_result(ptr, bytes.len() as u32)
+    pack_result(ptr as u32, bytes.len() as u32)
 }
 
-/// Read a string from WASM memory given pointer and length
-pub fn read_string(ptr: u32, len: u32) -> String {
+/// Read raw bytes from WASM memory given pointer and length.
+pub fn read_bytes(ptr: u32, len: u32) -> Vec<u8> {
     if len == 0 {
-        return String::new();
+        return Vec::new();
     }
     let bytes = unsafe { slice::from_raw_parts(ptr as *const u8, len as usize) };
-    String::from_utf8_lossy(bytes).into_owned()
+    bytes.to_vec()
 }
 
-/// Allocate memory for the host to write data
-/// 
+/// Allocate memory for the host to write data.
+///
+/// Unlike `write_string`/`write_bytes`, this goes straight to the system
+/// allocator: it's for host-owned buffers (e.g. a hook's input payload)
+/// whose lifetime must survive `reset_arena`, and which the host is
+/// expected to `free` explicitly.
+///
 /// # Safety
 /// This function is marked as safe but performs unsafe operations internally.
 /// It is intended to be called from WASM host.
@@ -52,8 +173,8 @@ pub extern "C" fn malloc(size: u32) -> u32 {
     unsafe { alloc(layout) as u32 }
 }
 
-/// Free allocated memory
-/// 
+/// Free memory previously returned by `malloc`.
+///
 /// # Safety
 /// This function is marked as safe but performs unsafe operations internally.
 /// It is intended to be called from WASM host.
@@ -0,0 +1,67 @@
+//! Typed body extraction for `HTTPRequest`, modeled on the ergonomic
+//! `RequestPayloadExt`-style helpers from AWS Lambda's Rust runtime: parse
+//! the raw body into a concrete type once, instead of every intercept
+//! plugin re-running `serde_json::from_slice` by hand and losing the
+//! original parse error in the process.
+
+use crate::types::HTTPRequest;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Error parsing an `HTTPRequest` body into a typed value.
+#[derive(Debug)]
+pub enum PayloadError {
+    Json(serde_json::Error),
+    WwwFormUrlEncoded(serde_urlencoded::de::Error),
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadError::Json(e) => write!(f, "invalid JSON body: {}", e),
+            PayloadError::WwwFormUrlEncoded(e) => write!(f, "invalid form body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PayloadError {}
+
+impl From<serde_json::Error> for PayloadError {
+    fn from(e: serde_json::Error) -> Self {
+        PayloadError::Json(e)
+    }
+}
+
+impl From<serde_urlencoded::de::Error> for PayloadError {
+    fn from(e: serde_urlencoded::de::Error) -> Self {
+        PayloadError::WwwFormUrlEncoded(e)
+    }
+}
+
+impl HTTPRequest {
+    fn content_type(&self) -> Option<&str> {
+        self.headers.get("content-type")
+    }
+
+    /// Parse the body as JSON, regardless of `content-type`.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, PayloadError> {
+        serde_json::from_slice(self.body.as_bytes()).map_err(PayloadError::from)
+    }
+
+    /// Parse the body as `application/x-www-form-urlencoded`, regardless
+    /// of `content-type`.
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, PayloadError> {
+        serde_urlencoded::from_bytes(self.body.as_bytes()).map_err(PayloadError::from)
+    }
+
+    /// Parse the body as whichever format its `content-type` header
+    /// indicates, defaulting to JSON when the header is absent or
+    /// unrecognized (most Bifrost request bodies, e.g. OpenAI-style chat
+    /// payloads, are JSON).
+    pub fn payload<T: DeserializeOwned>(&self) -> Result<T, PayloadError> {
+        match self.content_type() {
+            Some(ct) if ct.contains("x-www-form-urlencoded") => self.form(),
+            _ => self.json(),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Strongly-typed query-string deserialization for `HTTPRequest`.
+//!
+//! `HTTPRequest.query` arrives as a flat `HashMap<String, String>`, which
+//! can't represent nested query params (`filter[status]=open`) - and
+//! repeated keys (`id=1&id=2`) are already collapsed to one value by the
+//! time they reach the map, so only nesting survives the round trip.
+//! Re-serializing it back to a query string and running that through
+//! `serde_qs` gets plugins the structured deserialization they'd get
+//! parsing a URL directly, without Bifrost's host side needing to change
+//! how it hands query params to the plugin.
+
+use crate::types::HTTPRequest;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// Error deserializing `HTTPRequest.query` into a typed value.
+#[derive(Debug)]
+pub struct QueryError(serde_qs::Error);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid query string: {}", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl From<serde_qs::Error> for QueryError {
+    fn from(e: serde_qs::Error) -> Self {
+        QueryError(e)
+    }
+}
+
+impl HTTPRequest {
+    /// Deserialize `self.query` into `T` via `serde_qs`, supporting nested
+    /// params (`filter[status]=open`). An empty query map deserializes
+    /// against an empty string, so a `T` whose fields are all
+    /// `Option`/defaulted succeeds instead of erroring on a missing key.
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, QueryError> {
+        // serde_urlencoded percent-encodes `[`/`]`, which serde_qs (strict
+        // mode) would read as literal key characters rather than nesting.
+        let query_string = serde_urlencoded::to_string(&self.query)
+            .unwrap_or_default()
+            .replace("%5B", "[")
+            .replace("%5D", "]");
+        serde_qs::from_str(&query_string).map_err(QueryError::from)
+    }
+}
@@ -0,0 +1,108 @@
+//! Fluent `HTTPResponse` construction for intercept plugins that short-
+//! circuit the request (cache hit, auth rejection, canned error), mirroring
+//! the ergonomics of Azure Functions' HTTP output bindings. Keeps the
+//! Go-facing `status_code`/`headers`/`body` wire shape unchanged - this is
+//! purely sugar over hand-building an `HTTPResponse`.
+
+use crate::types::{Base64Body, HTTPResponse};
+
+/// A response body, convertible from the common shapes a plugin produces.
+/// `serde_json::Value` bodies set `content-type: application/json`
+/// automatically; the other conversions leave `content-type` unset so
+/// `.header()` (or the caller's own default) decides it.
+pub struct Body {
+    bytes: Vec<u8>,
+    content_type: Option<&'static str>,
+}
+
+impl From<String> for Body {
+    fn from(s: String) -> Self {
+        Body {
+            bytes: s.into_bytes(),
+            content_type: None,
+        }
+    }
+}
+
+impl From<&str> for Body {
+    fn from(s: &str) -> Self {
+        Body {
+            bytes: s.as_bytes().to_vec(),
+            content_type: None,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body {
+            bytes,
+            content_type: None,
+        }
+    }
+}
+
+impl From<serde_json::Value> for Body {
+    fn from(value: serde_json::Value) -> Self {
+        Body {
+            bytes: serde_json::to_vec(&value).unwrap_or_default(),
+            content_type: Some("application/json"),
+        }
+    }
+}
+
+/// Fluent builder for `HTTPResponse`, returned by `HTTPResponse::builder()`.
+#[derive(Debug, Default)]
+pub struct ResponseBuilder {
+    response: HTTPResponse,
+}
+
+impl ResponseBuilder {
+    /// Set the HTTP status code.
+    pub fn status(mut self, status: u16) -> Self {
+        self.response.status_code = status as i32;
+        self
+    }
+
+    /// Add a response header. Call this more than once with the same
+    /// `key` for headers that may legitimately repeat (`Set-Cookie`, ...).
+    /// `content-type` is a singleton and always overwrites instead,
+    /// whichever order it's set in relative to `.body()`'s automatic
+    /// `content-type` - otherwise the two would accumulate into two
+    /// `Content-Type` values on the wire.
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        if key.eq_ignore_ascii_case("content-type") {
+            self.response.headers.set(key, value);
+        } else {
+            self.response.headers.insert(key, value);
+        }
+        self
+    }
+
+    /// Set the response body. A `serde_json::Value` body sets
+    /// `content-type: application/json` unless a header was already set
+    /// explicitly.
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        let body = body.into();
+        if let Some(content_type) = body.content_type {
+            if !self.response.headers.contains_key("content-type") {
+                self.response.headers.set("content-type", content_type);
+            }
+        }
+        self.response.body = Base64Body(body.bytes);
+        self
+    }
+
+    /// Finish building and return the `HTTPResponse`.
+    pub fn build(self) -> HTTPResponse {
+        self.response
+    }
+}
+
+impl HTTPResponse {
+    /// Start building a response fluently, e.g.
+    /// `HTTPResponse::builder().status(200).body(json!({"ok": true})).build()`.
+    pub fn builder() -> ResponseBuilder {
+        ResponseBuilder::default()
+    }
+}
@@ -0,0 +1,191 @@
+//! JSON Schema validation for plugin-facing config and tool-call shapes,
+//! gated behind the `schema` feature so plugins that don't need it avoid
+//! the extra dependency weight.
+//!
+//! This is intentionally a small validator, not a full Draft 2020-12
+//! implementation: it covers the subset tool-calling plugins actually
+//! need to sanity-check `ToolCall.function.arguments` before forwarding
+//! them upstream - `type`, `required`, and `$ref`/`$defs` references - and
+//! reports the first violation as a JSON pointer so it can be dropped
+//! straight into `ErrorField.param`.
+
+use crate::types::{BifrostError, BifrostErrorCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A JSON Schema document. Wraps the raw `Value` so `$ref` lookups can walk
+/// back into the same document's `$defs`/`definitions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Schema(pub Value);
+
+impl Schema {
+    /// Validate `value` against this schema, returning a `BifrostError`
+    /// describing the first violation found, with the failing location as
+    /// a JSON pointer in `error.param`.
+    pub fn validate(&self, value: &Value) -> Result<(), BifrostError> {
+        match self.check(&self.0, value, "") {
+            Some((pointer, message)) => Err(BifrostError::new(&message)
+                .with_error_code(BifrostErrorCode::SchemaValidation)
+                .with_param(&pointer)),
+            None => Ok(()),
+        }
+    }
+
+    fn resolve<'a>(&'a self, schema: &'a Value) -> &'a Value {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => {
+                let path = reference.trim_start_matches("#/");
+                let mut cursor = &self.0;
+                for segment in path.split('/') {
+                    cursor = match cursor.get(segment) {
+                        Some(next) => next,
+                        None => return schema,
+                    };
+                }
+                cursor
+            }
+            None => schema,
+        }
+    }
+
+    /// Walk `value` against `schema`, returning the pointer and message of
+    /// the first violation found, if any.
+    fn check(&self, schema: &Value, value: &Value, pointer: &str) -> Option<(String, String)> {
+        let schema = self.resolve(schema);
+
+        if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+            if !matches_type(expected, value) {
+                return Some((
+                    pointer.to_string(),
+                    format!("expected type \"{}\", got {}", expected, type_name(value)),
+                ));
+            }
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+
+            let object = value.as_object();
+
+            for field in &required {
+                if object.map(|o| !o.contains_key(*field)).unwrap_or(true) {
+                    return Some((
+                        format!("{}/{}", pointer, field),
+                        format!("missing required field \"{}\"", field),
+                    ));
+                }
+            }
+
+            if let Some(object) = object {
+                for (key, child_schema) in properties {
+                    if let Some(child_value) = object.get(key) {
+                        let child_pointer = format!("{}/{}", pointer, key);
+                        if let Some(violation) = self.check(child_schema, child_value, &child_pointer) {
+                            return Some(violation);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(item_schema) = schema.get("items") {
+            if let Some(items) = value.as_array() {
+                for (index, item) in items.iter().enumerate() {
+                    let child_pointer = format!("{}/{}", pointer, index);
+                    if let Some(violation) = self.check(item_schema, item, &child_pointer) {
+                        return Some(violation);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_passes_for_matching_value() {
+        let schema = Schema(json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {"name": {"type": "string"}},
+        }));
+
+        assert!(schema.validate(&json!({"name": "tool"})).is_ok());
+    }
+
+    #[test]
+    fn test_validate_reports_pointer_for_missing_required_field() {
+        let schema = Schema(json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {"name": {"type": "string"}},
+        }));
+
+        let error = schema.validate(&json!({})).unwrap_err();
+        assert_eq!(error.error.param.as_deref(), Some("/name"));
+        assert_eq!(error.error.error_code, Some(BifrostErrorCode::SchemaValidation));
+    }
+
+    #[test]
+    fn test_validate_reports_pointer_for_nested_type_mismatch() {
+        let schema = Schema(json!({
+            "type": "object",
+            "properties": {
+                "items": {
+                    "type": "array",
+                    "items": {"type": "integer"},
+                },
+            },
+        }));
+
+        let error = schema.validate(&json!({"items": [1, "two", 3]})).unwrap_err();
+        assert_eq!(error.error.param.as_deref(), Some("/items/1"));
+    }
+
+    #[test]
+    fn test_validate_resolves_ref_into_defs() {
+        let schema = Schema(json!({
+            "$defs": {"name": {"type": "string"}},
+            "type": "object",
+            "properties": {"name": {"$ref": "#/$defs/name"}},
+        }));
+
+        let error = schema.validate(&json!({"name": 42})).unwrap_err();
+        assert_eq!(error.error.param.as_deref(), Some("/name"));
+    }
+}
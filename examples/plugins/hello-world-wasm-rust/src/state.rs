@@ -0,0 +1,128 @@
+//! Host-backed persistent state store for WASM plugins.
+//!
+//! A plugin instance has no durable memory of its own between invocations
+//! (wasmtime may even recycle the instance), so anything a hook needs to
+//! remember across calls - rate-limit counters, caches, circuit-breaker
+//! state - has to live on the host side. This module declares the host
+//! import functions for a simple key/value store and wraps them in a safe
+//! `StateStore` API built on the existing `read_string`/`write_string`
+//! memory helpers.
+
+use crate::memory::{read_string, write_string};
+use serde::{Deserialize, Serialize};
+
+extern "C" {
+    /// Fetch the value stored under `key` on the host.
+    /// Returns a packed ptr+len (see `memory::pack_result`), or 0 if unset.
+    fn host_state_get(key_ptr: u32, key_len: u32) -> u64;
+
+    /// Store `value` under `key` on the host, overwriting any prior value.
+    fn host_state_set(key_ptr: u32, key_len: u32, val_ptr: u32, val_len: u32);
+
+    /// Current wall-clock time in milliseconds, as tracked by the host.
+    fn host_now_millis() -> u64;
+}
+
+/// Unpack a `pack_result`-style u64 into its ptr/len parts.
+fn unpack(packed: u64) -> (u32, u32) {
+    ((packed >> 32) as u32, packed as u32)
+}
+
+/// Safe wrapper around the host-backed key/value store.
+///
+/// Keys and values are plain strings at this layer; `get_json`/`set_json`
+/// add a JSON round-trip on top for plugins that need structured state.
+pub struct StateStore;
+
+impl StateStore {
+    /// Read the raw string stored under `key`, if any.
+    pub fn get(key: &str) -> Option<String> {
+        let (kp, kl) = unpack(write_string(key));
+        let packed = unsafe { host_state_get(kp, kl) };
+        if packed == 0 {
+            return None;
+        }
+        let (vp, vl) = unpack(packed);
+        Some(read_string(vp, vl))
+    }
+
+    /// Store `value` under `key`.
+    pub fn set(key: &str, value: &str) {
+        let (kp, kl) = unpack(write_string(key));
+        let (vp, vl) = unpack(write_string(value));
+        unsafe { host_state_set(kp, kl, vp, vl) };
+    }
+
+    /// Read and JSON-decode the value stored under `key`.
+    pub fn get_json<T: for<'de> Deserialize<'de>>(key: &str) -> Option<T> {
+        Self::get(key).and_then(|s| serde_json::from_str(&s).ok())
+    }
+
+    /// JSON-encode `value` and store it under `key`.
+    pub fn set_json<T: Serialize>(key: &str, value: &T) {
+        if let Ok(s) = serde_json::to_string(value) {
+            Self::set(key, &s);
+        }
+    }
+
+    /// Current time in milliseconds, per the host clock.
+    pub fn now_millis() -> u64 {
+        unsafe { host_now_millis() }
+    }
+}
+
+/// Token-bucket state for a single rate-limit key, persisted via `StateStore`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill_ms: u64,
+}
+
+/// Parameters governing a token bucket's capacity and refill rate.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitConfig {
+    /// Maximum number of tokens the bucket can hold.
+    pub capacity: f64,
+    /// Tokens added per second.
+    pub refill_rate: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10.0,
+            refill_rate: 1.0,
+        }
+    }
+}
+
+/// Check and consume one token from the bucket identified by `key`.
+///
+/// Returns `true` if the call should be rate-limited (no tokens were
+/// available), `false` if a token was consumed and the call may proceed.
+/// State is refilled lazily based on elapsed time since the last call,
+/// so no background task is needed to "tick" the bucket.
+pub fn should_rate_limit(key: &str, config: &RateLimitConfig) -> bool {
+    let state_key = format!("rate_limit:{}", key);
+    let now = StateStore::now_millis();
+
+    let mut bucket = StateStore::get_json::<TokenBucket>(&state_key).unwrap_or(TokenBucket {
+        tokens: config.capacity,
+        last_refill_ms: now,
+    });
+
+    let elapsed_ms = now.saturating_sub(bucket.last_refill_ms);
+    let refilled = (elapsed_ms as f64 / 1000.0) * config.refill_rate;
+    bucket.tokens = (bucket.tokens + refilled).min(config.capacity);
+    bucket.last_refill_ms = now;
+
+    let limited = if bucket.tokens >= 1.0 {
+        bucket.tokens -= 1.0;
+        false
+    } else {
+        true
+    };
+
+    StateStore::set_json(&state_key, &bucket);
+    limited
+}
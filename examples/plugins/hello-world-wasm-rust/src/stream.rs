@@ -0,0 +1,313 @@
+//! Cross-chunk streaming state for `http_stream_chunk_hook`.
+//!
+//! A single hook invocation only sees one SSE frame in isolation, so
+//! transformations that span chunk boundaries - e.g. redacting a secret
+//! that gets split across two frames, or reasoning about the full message
+//! a stream of deltas is building up to - can't be expressed with the
+//! chunk hook's pass-through/skip/stop vocabulary alone. `StreamBuffer`
+//! gives a handler a per-stream scratch buffer for raw text;
+//! `StreamAccumulator` goes further and reassembles a full
+//! `BifrostChatResponse` out of successive `ResponseChoice.delta`
+//! fragments. `StreamBuffer` is keyed by `context.request_id` and persisted
+//! via `StateStore` across calls; `StreamAccumulator` is serializable but
+//! does not persist itself, so the handler stores it the same way.
+
+use crate::state::StateStore;
+use crate::types::{
+    BifrostChatResponse, ChatMessage, ChatMessageContent, ChatMessageRole, LLMUsage, ResponseChoice,
+    ToolCall,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Scratch state persisted between chunk invocations for one stream.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BufferState {
+    pending: String,
+}
+
+/// Per-stream scratch buffer, loaded from and saved back to `StateStore`.
+pub struct StreamBuffer {
+    key: String,
+    pending: String,
+}
+
+impl StreamBuffer {
+    fn state_key(request_id: &str) -> String {
+        format!("stream_buffer:{}", request_id)
+    }
+
+    /// Load the scratch buffer for `request_id`, picking up whatever was
+    /// held back from a prior chunk (empty if this is the first chunk).
+    pub fn load(request_id: &str) -> Self {
+        let key = Self::state_key(request_id);
+        let pending = StateStore::get_json::<BufferState>(&key)
+            .map(|s| s.pending)
+            .unwrap_or_default();
+        Self { key, pending }
+    }
+
+    /// Append this chunk's text to the accumulated tail.
+    pub fn append(&mut self, text: &str) {
+        self.pending.push_str(text);
+    }
+
+    /// The full accumulated tail so far: whatever was held back from
+    /// earlier chunks plus anything appended this call.
+    pub fn tail(&self) -> &str {
+        &self.pending
+    }
+
+    /// Persist `remainder` so the next chunk for this stream picks up
+    /// where this one left off. Used when a handler holds a chunk back
+    /// (e.g. a potential match isn't complete yet).
+    pub fn hold(self, remainder: &str) {
+        StateStore::set_json(
+            &self.key,
+            &BufferState {
+                pending: remainder.to_string(),
+            },
+        );
+    }
+
+    /// Clear the buffer. Call once the stream is known to be done (the
+    /// final chunk) so state doesn't leak across requests.
+    pub fn flush(self) {
+        StateStore::set(&self.key, "");
+    }
+}
+
+/// Per-choice accumulation state: merged delta text, streamed tool-call
+/// arguments keyed by their index in the delta array (tool calls can
+/// interleave across chunks, so they're not necessarily seen in order),
+/// and the terminal `finish_reason` once it arrives.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChoiceAccumulator {
+    role: ChatMessageRole,
+    content: String,
+    tool_calls: BTreeMap<usize, ToolCall>,
+    finish_reason: Option<String>,
+}
+
+/// Reassembles a full `BifrostChatResponse` out of successive
+/// `ResponseChoice.delta` fragments, so a chunk hook can make decisions on
+/// the whole response so far while still streaming. Store and retrieve one
+/// per request (e.g. via `StateStore`, keyed by `context.request_id`,
+/// the same way `StreamBuffer` is).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StreamAccumulator {
+    id: String,
+    model: String,
+    created: Option<i64>,
+    choices: BTreeMap<i32, ChoiceAccumulator>,
+    usage: Option<LLMUsage>,
+    complete: bool,
+}
+
+impl StreamAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge one chunk's deltas into the accumulated response. Handles the
+    /// terminal `[DONE]` sentinel (a bare JSON string, not an object) and
+    /// usage-only trailing chunks by marking the accumulator complete
+    /// without requiring a `finish_reason` on every choice.
+    pub fn accumulate(&mut self, chunk: &Value) {
+        if chunk.as_str() == Some("[DONE]") {
+            self.complete = true;
+            return;
+        }
+
+        if let Some(id) = chunk.get("id").and_then(Value::as_str) {
+            self.id = id.to_string();
+        }
+        if let Some(model) = chunk.get("model").and_then(Value::as_str) {
+            self.model = model.to_string();
+        }
+        if let Some(created) = chunk.get("created").and_then(Value::as_i64) {
+            self.created = Some(created);
+        }
+        if let Some(usage) = chunk
+            .get("usage")
+            .and_then(|u| serde_json::from_value::<LLMUsage>(u.clone()).ok())
+        {
+            self.usage = Some(usage);
+            self.complete = true;
+        }
+
+        let Some(choices) = chunk.get("choices").and_then(Value::as_array) else {
+            return;
+        };
+
+        for choice in choices {
+            let index = choice.get("index").and_then(Value::as_i64).unwrap_or(0) as i32;
+            let entry = self.choices.entry(index).or_default();
+
+            if let Some(finish_reason) = choice.get("finish_reason").and_then(Value::as_str) {
+                entry.finish_reason = Some(finish_reason.to_string());
+                self.complete = true;
+            }
+
+            let Some(delta) = choice.get("delta") else {
+                continue;
+            };
+
+            if let Some(role) = delta
+                .get("role")
+                .and_then(Value::as_str)
+                .and_then(|r| serde_json::from_value(Value::String(r.to_string())).ok())
+            {
+                entry.role = role;
+            }
+
+            if let Some(content) = delta.get("content").and_then(Value::as_str) {
+                entry.content.push_str(content);
+            }
+
+            if let Some(tool_calls) = delta.get("tool_calls").and_then(Value::as_array) {
+                for tool_call in tool_calls {
+                    let tc_index = tool_call.get("index").and_then(Value::as_u64).unwrap_or(0) as usize;
+                    let slot = entry.tool_calls.entry(tc_index).or_default();
+
+                    if let Some(id) = tool_call.get("id").and_then(Value::as_str) {
+                        slot.id = Some(id.to_string());
+                    }
+                    if let Some(call_type) = tool_call.get("type").and_then(Value::as_str) {
+                        slot.call_type = Some(call_type.to_string());
+                    }
+                    if let Some(function) = tool_call.get("function") {
+                        if let Some(name) = function.get("name").and_then(Value::as_str) {
+                            slot.function.name = Some(name.to_string());
+                        }
+                        if let Some(arguments) = function.get("arguments").and_then(Value::as_str) {
+                            slot.function.arguments.push_str(arguments);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the stream has reached a terminal chunk: a `finish_reason`
+    /// on some choice, a usage-only trailing chunk, or `[DONE]`.
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Render the accumulated deltas as a `BifrostChatResponse`, as it
+    /// stands so far.
+    pub fn snapshot(&self) -> BifrostChatResponse {
+        let choices = self
+            .choices
+            .iter()
+            .map(|(&index, acc)| {
+                let tool_calls: Vec<ToolCall> = acc.tool_calls.values().cloned().collect();
+                ResponseChoice {
+                    index,
+                    message: Some(ChatMessage {
+                        role: acc.role.clone(),
+                        content: Some(ChatMessageContent::Text(acc.content.clone())),
+                        tool_calls: if tool_calls.is_empty() {
+                            None
+                        } else {
+                            Some(tool_calls)
+                        },
+                        ..Default::default()
+                    }),
+                    delta: None,
+                    finish_reason: acc.finish_reason.clone(),
+                    logprobs: None,
+                }
+            })
+            .collect();
+
+        BifrostChatResponse {
+            id: self.id.clone(),
+            model: self.model.clone(),
+            choices,
+            usage: self.usage.clone(),
+            created: self.created,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_accumulate_merges_interleaved_tool_call_indices() {
+        let mut acc = StreamAccumulator::new();
+
+        acc.accumulate(&json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-4",
+            "choices": [{
+                "index": 0,
+                "delta": {"role": "assistant", "tool_calls": [
+                    {"index": 1, "id": "call_b", "type": "function", "function": {"name": "second"}},
+                ]},
+            }],
+        }));
+        acc.accumulate(&json!({
+            "choices": [{
+                "index": 0,
+                "delta": {"tool_calls": [
+                    {"index": 0, "id": "call_a", "type": "function", "function": {"name": "first", "arguments": "{\"x\":"}},
+                ]},
+            }],
+        }));
+        acc.accumulate(&json!({
+            "choices": [{
+                "index": 0,
+                "delta": {"tool_calls": [
+                    {"index": 0, "function": {"arguments": "1}"}},
+                    {"index": 1, "function": {"arguments": "{}"}},
+                ]},
+            }],
+        }));
+
+        let snapshot = acc.snapshot();
+        let tool_calls = snapshot.choices[0].message.as_ref().unwrap().tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls.len(), 2);
+        assert_eq!(tool_calls[0].id.as_deref(), Some("call_a"));
+        assert_eq!(tool_calls[0].function.name.as_deref(), Some("first"));
+        assert_eq!(tool_calls[0].function.arguments, "{\"x\":1}");
+        assert_eq!(tool_calls[1].id.as_deref(), Some("call_b"));
+        assert_eq!(tool_calls[1].function.arguments, "{}");
+    }
+
+    #[test]
+    fn test_accumulate_handles_done_sentinel() {
+        let mut acc = StreamAccumulator::new();
+        assert!(!acc.is_complete());
+        acc.accumulate(&json!("[DONE]"));
+        assert!(acc.is_complete());
+    }
+
+    #[test]
+    fn test_accumulate_usage_only_chunk_marks_complete() {
+        let mut acc = StreamAccumulator::new();
+        acc.accumulate(&json!({
+            "id": "chatcmpl-1",
+            "choices": [{"index": 0, "delta": {"content": "hi"}}],
+        }));
+        assert!(!acc.is_complete());
+
+        acc.accumulate(&json!({
+            "usage": {"prompt_tokens": 10, "completion_tokens": 2, "total_tokens": 12},
+        }));
+
+        assert!(acc.is_complete());
+        let snapshot = acc.snapshot();
+        assert_eq!(snapshot.usage.unwrap().total_tokens, 12);
+        match snapshot.choices[0].message.as_ref().unwrap().content.as_ref().unwrap() {
+            ChatMessageContent::Text(text) => assert_eq!(text, "hi"),
+            other => panic!("expected text content, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Backend-agnostic telemetry envelope, emitted from `post_hook` so a
+//! plugin can record cost and latency per request without coupling to any
+//! particular observability backend.
+//!
+//! Framing is inspired by the Sentry envelope format: a header line
+//! identifying the request, followed by newline-delimited JSON items. The
+//! host reads the serialized envelope back out of `context["telemetry"]`
+//! and ships it wherever it likes.
+
+use crate::state::StateStore;
+use crate::types::LLMUsage;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// Envelope header: identifies which request this telemetry belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeHeader {
+    pub request_id: String,
+}
+
+/// Start/end timestamps for a single hook invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub hook: String,
+    pub started_at_ms: u64,
+    pub ended_at_ms: u64,
+    pub duration_ms: u64,
+}
+
+impl Transaction {
+    /// Close out a transaction started at `started_at_ms` (typically
+    /// recorded via `StateStore::now_millis()` at the top of the hook),
+    /// stamping the end time as now.
+    pub fn new(hook: &str, started_at_ms: u64) -> Self {
+        let ended_at_ms = StateStore::now_millis();
+        Self {
+            hook: hook.to_string(),
+            started_at_ms,
+            ended_at_ms,
+            duration_ms: ended_at_ms.saturating_sub(started_at_ms),
+        }
+    }
+}
+
+/// Token usage and resolved provider/model for the request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metrics {
+    pub provider: String,
+    pub model: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<LLMUsage>,
+}
+
+/// One NDJSON item in the envelope body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelemetryItem {
+    Transaction(Transaction),
+    Metrics(Metrics),
+}
+
+/// A Sentry-style envelope: one header line followed by newline-delimited
+/// JSON items.
+#[derive(Debug, Clone, Default)]
+pub struct TelemetryEnvelope {
+    request_id: String,
+    items: Vec<TelemetryItem>,
+}
+
+impl TelemetryEnvelope {
+    /// Start a new envelope for `request_id` (pulled from
+    /// `BifrostContext::get_string("request_id")`).
+    pub fn new(request_id: &str) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            items: Vec::new(),
+        }
+    }
+
+    pub fn record_transaction(&mut self, transaction: Transaction) {
+        self.items.push(TelemetryItem::Transaction(transaction));
+    }
+
+    pub fn record_metrics(&mut self, metrics: Metrics) {
+        self.items.push(TelemetryItem::Metrics(metrics));
+    }
+
+    /// Stream the envelope as NDJSON: a header line, then one line per
+    /// item. Unlike Sentry's `application/x-sentry-envelope` framing, items
+    /// carry no separate item-header line; the `type` tag is inline.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let header = EnvelopeHeader {
+            request_id: self.request_id.clone(),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&header).unwrap_or_default())?;
+        for item in &self.items {
+            writeln!(writer, "{}", serde_json::to_string(item).unwrap_or_default())?;
+        }
+        Ok(())
+    }
+
+    /// Render the envelope to an NDJSON string, for stashing into the
+    /// context via `PostHookOutput::attach_telemetry`.
+    pub fn to_ndjson(&self) -> String {
+        let mut buf = Vec::new();
+        let _ = self.to_writer(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+}
@@ -2,6 +2,7 @@
 //! These structures mirror the Go SDK types for interoperability.
 
 use serde::{Deserialize, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
 use std::collections::HashMap;
 
 // =============================================================================
@@ -133,12 +134,211 @@ impl BifrostContext {
     }
 }
 
+// =============================================================================
+// Base64 Body
+// =============================================================================
+
+/// Raw bytes for an HTTP request/response body, transparently base64-coded
+/// on the wire. Plugin authors read/write `Base64Body` directly instead of
+/// calling a base64 decoder/encoder by hand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Base64Body(pub Vec<u8>);
+
+impl Base64Body {
+    /// Raw bytes of the body.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The body as text, or `None` if it isn't valid UTF-8 (compressed,
+    /// image, or other binary content). Prefer this over
+    /// `as_str_lossy` when you need to know whether the body was
+    /// actually text before acting on it.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+
+    /// Decode the body as UTF-8 text, replacing invalid sequences rather
+    /// than failing. Source-compatible with callers that only ever dealt
+    /// with string bodies; prefer `as_str`/`is_text` when the body might
+    /// be binary.
+    pub fn as_str_lossy(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    /// Whether the body is valid UTF-8 text, as opposed to binary content.
+    pub fn is_text(&self) -> bool {
+        self.as_str().is_some()
+    }
+}
+
+impl From<Vec<u8>> for Base64Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<&str> for Base64Body {
+    fn from(s: &str) -> Self {
+        Self(s.as_bytes().to_vec())
+    }
+}
+
+/// Serde helper that (de)serializes a `Base64Body` as a base64 string on
+/// the wire, mirroring how `nullable` handles Go's null-for-empty
+/// convention: a missing/null field decodes to an empty body rather than
+/// an error.
+mod base64bytes {
+    use super::Base64Body;
+    use base64::{engine::general_purpose::STANDARD, Engine as _};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(body: &Base64Body, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        STANDARD.encode(&body.0).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Base64Body, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(Base64Body::default()),
+            Some(s) if s.is_empty() => Ok(Base64Body::default()),
+            Some(s) => STANDARD
+                .decode(s)
+                .map(Base64Body)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// =============================================================================
+// Headers
+// =============================================================================
+
+/// Canonicalize a header name the way Go's `textproto.CanonicalMIMEHeaderKey`
+/// does (`content-type` -> `Content-Type`), so lookups are case-insensitive
+/// and the wire form matches what the Go side of Bifrost produces.
+fn canonicalize_header_name(name: &str) -> String {
+    name.split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => {
+                    first.to_ascii_uppercase().to_string() + &chars.as_str().to_ascii_lowercase()
+                }
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// HTTP headers that preserve repeated values (`Set-Cookie`, multiple
+/// `Via`, ...) instead of collapsing to one value per name, with
+/// case-insensitive lookup. Round-trips Go's `map[string][]string` header
+/// representation, and also accepts a single-string-per-key map on
+/// deserialize for callers that send unrepeated headers that way.
+#[derive(Debug, Clone, Default)]
+pub struct Headers(HashMap<String, Vec<String>>);
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value for `name`, keeping any existing values (use this for
+    /// headers that may legitimately repeat).
+    pub fn insert(&mut self, name: &str, value: impl Into<String>) {
+        self.0
+            .entry(canonicalize_header_name(name))
+            .or_default()
+            .push(value.into());
+    }
+
+    /// Set `name` to a single value, discarding any existing values.
+    pub fn set(&mut self, name: &str, value: impl Into<String>) {
+        self.0
+            .insert(canonicalize_header_name(name), vec![value.into()]);
+    }
+
+    /// The first value for `name`, if present.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .get(&canonicalize_header_name(name))
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
+    /// All values for `name`, in the order they were added.
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.0
+            .get(&canonicalize_header_name(name))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.0.contains_key(&canonicalize_header_name(name))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<String>)> {
+        self.0.iter()
+    }
+}
+
+impl Serialize for Headers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawValue {
+            Single(String),
+            Multi(Vec<String>),
+        }
+
+        // A per-key value may also be `null` (Go's JSON encoder emits null for
+        // nil slice values), so deserialize values as `Option<RawValue>` and
+        // drop the nulls, same convention as `nullable::string_map`.
+        let raw = Option::<HashMap<String, Option<RawValue>>>::deserialize(deserializer)?;
+        let mut headers = Headers::default();
+        for (name, value) in raw.into_iter().flatten() {
+            let Some(value) = value else {
+                continue;
+            };
+            let values = match value {
+                RawValue::Single(s) => vec![s],
+                RawValue::Multi(v) => v,
+            };
+            headers.0.insert(canonicalize_header_name(&name), values);
+        }
+        Ok(headers)
+    }
+}
+
 // =============================================================================
 // HTTP Transport Structures
 // =============================================================================
 
 /// HTTPRequest represents an incoming HTTP request at the transport layer.
-/// Body is base64-encoded.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct HTTPRequest {
     #[serde(default, deserialize_with = "nullable::string")]
@@ -147,15 +347,14 @@ pub struct HTTPRequest {
     #[serde(default, deserialize_with = "nullable::string")]
     pub path: String,
 
-    #[serde(default, deserialize_with = "nullable::string_map")]
-    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: Headers,
 
     #[serde(default, deserialize_with = "nullable::string_map")]
     pub query: HashMap<String, String>,
 
-    /// Base64-encoded request body
-    #[serde(default, deserialize_with = "nullable::string")]
-    pub body: String,
+    #[serde(default, with = "base64bytes")]
+    pub body: Base64Body,
 }
 
 /// HTTPResponse represents an HTTP response to return.
@@ -164,12 +363,11 @@ pub struct HTTPResponse {
     #[serde(default, deserialize_with = "nullable::i32_field")]
     pub status_code: i32,
 
-    #[serde(default, deserialize_with = "nullable::string_map")]
-    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub headers: Headers,
 
-    /// Base64-encoded response body
-    #[serde(default, deserialize_with = "nullable::string")]
-    pub body: String,
+    #[serde(default, with = "base64bytes")]
+    pub body: Base64Body,
 }
 
 /// HTTPInterceptInput is the input for http_intercept hook.
@@ -200,6 +398,15 @@ pub struct HTTPInterceptOutput {
     pub error: String,
 }
 
+impl crate::dispatch::HasErrorField for HTTPInterceptOutput {
+    fn with_error(message: String) -> Self {
+        Self {
+            error: message,
+            ..Default::default()
+        }
+    }
+}
+
 // =============================================================================
 // Chat Completion Structures (BifrostRequest)
 // =============================================================================
@@ -340,6 +547,7 @@ pub struct ChatTool {
 
 /// ChatToolFunction represents a function definition.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ChatToolFunction {
     pub name: String,
 
@@ -350,6 +558,18 @@ pub struct ChatToolFunction {
     pub parameters: Option<serde_json::Value>,
 }
 
+#[cfg(feature = "schema")]
+impl ChatToolFunction {
+    /// Parse `self.parameters` as a JSON Schema, so callers can validate
+    /// `ToolCall.function.arguments` against it before forwarding the call
+    /// upstream. Returns `None` if no parameters schema was declared.
+    pub fn parameters_schema(&self) -> Option<crate::schema::Schema> {
+        self.parameters
+            .clone()
+            .map(crate::schema::Schema)
+    }
+}
+
 /// BifrostChatRequest represents a chat completion request.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BifrostChatRequest {
@@ -376,26 +596,145 @@ pub struct Fallback {
     pub model: String,
 }
 
+/// BifrostEmbeddingRequest represents an embeddings request.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostEmbeddingRequest {
+    #[serde(default)]
+    pub provider: String,
+
+    #[serde(default)]
+    pub model: String,
+
+    #[serde(default)]
+    pub input: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<String>,
+}
+
+/// BifrostSpeechRequest represents a text-to-speech request.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostSpeechRequest {
+    #[serde(default)]
+    pub provider: String,
+
+    #[serde(default)]
+    pub model: String,
+
+    #[serde(default)]
+    pub input: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+}
+
+/// BifrostTranscriptionRequest represents a speech-to-text request.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostTranscriptionRequest {
+    #[serde(default)]
+    pub provider: String,
+
+    #[serde(default)]
+    pub model: String,
+
+    /// Base64-encoded audio payload
+    #[serde(default, with = "base64bytes")]
+    pub file: Base64Body,
+}
+
+/// BifrostImageRequest represents an image generation request.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostImageRequest {
+    #[serde(default)]
+    pub provider: String,
+
+    #[serde(default)]
+    pub model: String,
+
+    #[serde(default)]
+    pub prompt: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+}
+
+/// Identifies which modality a `BifrostRequest`/`BifrostResponse` carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Chat,
+    Embedding,
+    Speech,
+    Transcription,
+    Image,
+    Unknown,
+}
+
 /// BifrostRequest is the unified request structure.
-/// Only one of the request types should be present.
+/// Exactly one of the typed request fields should be present; anything
+/// else falls into `extra` and is reported as `RequestKind::Unknown`.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BifrostRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chat_request: Option<BifrostChatRequest>,
 
-    // Add other request types as needed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_request: Option<BifrostEmbeddingRequest>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speech_request: Option<BifrostSpeechRequest>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transcription_request: Option<BifrostTranscriptionRequest>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_request: Option<BifrostImageRequest>,
+
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
 impl BifrostRequest {
-    /// Get provider and model from the request
+    /// Which modality this request carries.
+    pub fn kind(&self) -> RequestKind {
+        if self.chat_request.is_some() {
+            RequestKind::Chat
+        } else if self.embedding_request.is_some() {
+            RequestKind::Embedding
+        } else if self.speech_request.is_some() {
+            RequestKind::Speech
+        } else if self.transcription_request.is_some() {
+            RequestKind::Transcription
+        } else if self.image_request.is_some() {
+            RequestKind::Image
+        } else {
+            RequestKind::Unknown
+        }
+    }
+
+    /// Get provider and model from whichever request variant is present.
     pub fn get_provider_model(&self) -> (String, String) {
-        if let Some(ref chat) = self.chat_request {
-            return (chat.provider.clone(), chat.model.clone());
+        if let Some(ref r) = self.chat_request {
+            return (r.provider.clone(), r.model.clone());
+        }
+        if let Some(ref r) = self.embedding_request {
+            return (r.provider.clone(), r.model.clone());
+        }
+        if let Some(ref r) = self.speech_request {
+            return (r.provider.clone(), r.model.clone());
+        }
+        if let Some(ref r) = self.transcription_request {
+            return (r.provider.clone(), r.model.clone());
+        }
+        if let Some(ref r) = self.image_request {
+            return (r.provider.clone(), r.model.clone());
         }
         (String::new(), String::new())
     }
+
+    /// Get just the model from whichever request variant is present.
+    pub fn get_model(&self) -> String {
+        self.get_provider_model().1
+    }
 }
 
 // =============================================================================
@@ -465,20 +804,135 @@ pub struct BifrostChatResponse {
     pub system_fingerprint: Option<String>,
 }
 
+/// A single embedding result within a `BifrostEmbeddingResponse`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EmbeddingData {
+    #[serde(default)]
+    pub index: i32,
+
+    #[serde(default)]
+    pub embedding: Vec<f64>,
+}
+
+/// BifrostEmbeddingResponse represents an embeddings response.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostEmbeddingResponse {
+    #[serde(default)]
+    pub model: String,
+
+    #[serde(default)]
+    pub data: Vec<EmbeddingData>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<LLMUsage>,
+}
+
+/// BifrostAudioResponse represents a speech (TTS) or transcription response.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostAudioResponse {
+    /// Base64-encoded audio payload, present on speech (TTS) responses.
+    #[serde(default, with = "base64bytes")]
+    pub audio: Base64Body,
+
+    /// Transcribed text, present on transcription responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// A single generated image within a `BifrostImageResponse`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ImageData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub b64_json: Option<String>,
+}
+
+/// BifrostImageResponse represents an image generation response.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BifrostImageResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<i64>,
+
+    #[serde(default)]
+    pub data: Vec<ImageData>,
+}
+
 /// BifrostResponse is the unified response structure.
+/// Exactly one of the typed response fields should be present; anything
+/// else falls into `extra`.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BifrostResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub chat_response: Option<BifrostChatResponse>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub embedding_response: Option<BifrostEmbeddingResponse>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_response: Option<BifrostAudioResponse>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_response: Option<BifrostImageResponse>,
+
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+impl BifrostResponse {
+    /// Which modality this response carries.
+    pub fn kind(&self) -> RequestKind {
+        if self.chat_response.is_some() {
+            RequestKind::Chat
+        } else if self.embedding_response.is_some() {
+            RequestKind::Embedding
+        } else if let Some(ref audio) = self.audio_response {
+            if audio.text.is_some() {
+                RequestKind::Transcription
+            } else {
+                RequestKind::Speech
+            }
+        } else if self.image_response.is_some() {
+            RequestKind::Image
+        } else {
+            RequestKind::Unknown
+        }
+    }
+}
+
 // =============================================================================
 // Error Structure
 // =============================================================================
 
+/// Stable, machine-readable error codes for `BifrostError`, modeled on the
+/// JSON-RPC error-code convention so plugins can branch on error category
+/// instead of parsing `message` text. Serializes as its integer
+/// discriminant via `serde_repr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize_repr, Deserialize_repr)]
+#[repr(i32)]
+pub enum BifrostErrorCode {
+    InvalidParams = -32602,
+    RateLimited = -32000,
+    ProviderTimeout = -32001,
+    SchemaValidation = -32002,
+    UpstreamIo = -32003,
+    ContextLengthExceeded = -32004,
+}
+
+impl BifrostErrorCode {
+    /// Whether a request that failed with this code is generally safe to
+    /// retry (possibly against a fallback provider).
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            BifrostErrorCode::RateLimited
+                | BifrostErrorCode::ProviderTimeout
+                | BifrostErrorCode::UpstreamIo
+        )
+    }
+}
+
 /// ErrorField contains the error details.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ErrorField {
@@ -493,6 +947,9 @@ pub struct ErrorField {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub param: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<BifrostErrorCode>,
 }
 
 /// BifrostError represents an error response.
@@ -506,6 +963,9 @@ pub struct BifrostError {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_fallbacks: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
 }
 
 impl BifrostError {
@@ -537,6 +997,39 @@ impl BifrostError {
         self.status_code = Some(status);
         self
     }
+
+    /// Set the JSON pointer (or field name) the error applies to, e.g. a
+    /// schema validation failure's location.
+    pub fn with_param(mut self, param: &str) -> Self {
+        self.error.param = Some(param.to_string());
+        self
+    }
+
+    /// Attach a machine-readable `BifrostErrorCode`. Defaults
+    /// `allow_fallbacks` from the code's retryability unless it was
+    /// already set explicitly.
+    pub fn with_error_code(mut self, code: BifrostErrorCode) -> Self {
+        self.error.error_code = Some(code);
+        if self.allow_fallbacks.is_none() {
+            self.allow_fallbacks = Some(code.is_retryable());
+        }
+        self
+    }
+
+    /// Set how long (in seconds) the caller should wait before retrying.
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    /// Whether this error's code (if any) indicates the request is safe
+    /// to retry.
+    pub fn is_retryable(&self) -> bool {
+        self.error
+            .error_code
+            .map(BifrostErrorCode::is_retryable)
+            .unwrap_or(false)
+    }
 }
 
 // =============================================================================
@@ -609,6 +1102,15 @@ pub struct PreHookOutput {
     pub error: String,
 }
 
+impl crate::dispatch::HasErrorField for PreHookOutput {
+    fn with_error(message: String) -> Self {
+        Self {
+            error: message,
+            ..Default::default()
+        }
+    }
+}
+
 /// PostHookInput is the input for post_hook.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PostHookInput {
@@ -659,6 +1161,24 @@ pub struct PostHookOutput {
     pub hook_error: String,
 }
 
+impl crate::dispatch::HasErrorField for PostHookOutput {
+    fn with_error(message: String) -> Self {
+        Self {
+            hook_error: message,
+            ..Default::default()
+        }
+    }
+}
+
+impl PostHookOutput {
+    /// Stash a serialized telemetry envelope into the context under
+    /// `"telemetry"`, so the host can pull it out and ship it to a
+    /// backend without changing this hook's response shape.
+    pub fn attach_telemetry(&mut self, envelope: &crate::telemetry::TelemetryEnvelope) {
+        self.context.set_value("telemetry", envelope.to_ndjson());
+    }
+}
+
 // =============================================================================
 // HTTP Stream Chunk Hook Input/Output Structures
 // =============================================================================
@@ -691,21 +1211,50 @@ pub struct HTTPStreamChunkHookOutput {
     #[serde(default)]
     pub skip: bool,
 
+    /// `true` means "hold this chunk back - emit nothing yet". Unlike
+    /// `skip`, which drops the chunk for good, a buffered chunk's content
+    /// is expected to resurface folded into a later chunk once a
+    /// cross-chunk match completes or the stream ends.
+    #[serde(default)]
+    pub buffered: bool,
+
     #[serde(default)]
     pub error: String,
 }
 
+impl crate::dispatch::HasErrorField for HTTPStreamChunkHookOutput {
+    fn with_error(message: String) -> Self {
+        Self {
+            error: message,
+            ..Default::default()
+        }
+    }
+}
+
 // =============================================================================
 // Plugin Configuration
 // =============================================================================
 
 /// Plugin configuration (customize as needed)
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PluginConfig {
     #[serde(flatten)]
     pub values: HashMap<String, serde_json::Value>,
 }
 
+#[cfg(feature = "schema")]
+impl PluginConfig {
+    /// Validate these config values (or, equally, a `ToolCall.function`'s
+    /// parsed `arguments`) against `schema`, returning a structured
+    /// `BifrostError` with the failing JSON pointer in `error.param` on
+    /// the first violation.
+    pub fn validate_against(&self, schema: &crate::schema::Schema) -> Result<(), BifrostError> {
+        let value = serde_json::to_value(&self.values).unwrap_or_default();
+        schema.validate(&value)
+    }
+}
+
 // =============================================================================
 // Tests
 // =============================================================================
@@ -813,7 +1362,7 @@ mod tests {
         assert_eq!(req.path, "/v1/chat/completions");
         assert!(req.headers.is_empty());
         assert!(req.query.is_empty());
-        assert_eq!(req.body, "");
+        assert_eq!(req.body.as_bytes(), b"");
     }
 
     #[test]
@@ -829,7 +1378,7 @@ mod tests {
         assert_eq!(req.path, "/health");
         assert!(req.headers.is_empty());
         assert!(req.query.is_empty());
-        assert_eq!(req.body, "");
+        assert_eq!(req.body.as_bytes(), b"");
     }
 
     #[test]
@@ -850,8 +1399,8 @@ mod tests {
         assert_eq!(input.context.get_string("request_id"), Some("abc-123"));
         assert_eq!(input.request.method, "POST");
         assert_eq!(input.request.path, "/v1/chat/completions");
-        assert_eq!(input.request.headers.get("content-type"), Some(&"application/json".to_string()));
-        assert_eq!(input.request.body, "");
+        assert_eq!(input.request.headers.get("content-type"), Some("application/json"));
+        assert_eq!(input.request.body.as_bytes(), b"");
     }
 
     #[test]
@@ -861,10 +1410,96 @@ mod tests {
             "headers": null,
             "body": null
         }"#;
-        
+
         let resp: HTTPResponse = serde_json::from_str(json).unwrap();
         assert_eq!(resp.status_code, 0);
         assert!(resp.headers.is_empty());
-        assert_eq!(resp.body, "");
+        assert_eq!(resp.body.as_bytes(), b"");
+    }
+
+    #[test]
+    fn test_base64_body_round_trip() {
+        let body = Base64Body::from(b"hello world".to_vec());
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "base64bytes")] Base64Body);
+
+        let json = serde_json::to_string(&Wrapper(body.clone())).unwrap();
+        let Wrapper(decoded) = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, body);
+        assert_eq!(decoded.as_str(), Some("hello world"));
+        assert!(decoded.is_text());
+    }
+
+    #[test]
+    fn test_base64_body_malformed_is_serde_error() {
+        #[derive(Serialize, Deserialize)]
+        struct Wrapper(#[serde(with = "base64bytes")] Base64Body);
+
+        let result: Result<Wrapper, _> = serde_json::from_str(r#""not valid base64!!""#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_headers_case_insensitive_multi_value_round_trip() {
+        let mut headers = Headers::new();
+        headers.insert("Set-Cookie", "a=1");
+        headers.insert("set-cookie", "b=2");
+        headers.set("content-type", "application/json");
+
+        assert_eq!(headers.get("SET-COOKIE"), Some("a=1"));
+        assert_eq!(headers.get_all("set-cookie"), &["a=1".to_string(), "b=2".to_string()]);
+        assert_eq!(headers.get("Content-Type"), Some("application/json"));
+
+        let json = serde_json::to_string(&headers).unwrap();
+        let round_tripped: Headers = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.get_all("set-cookie"), &["a=1".to_string(), "b=2".to_string()]);
+        assert_eq!(round_tripped.get("content-type"), Some("application/json"));
+    }
+
+    #[test]
+    fn test_headers_single_and_null_values() {
+        let json = r#"{"Content-Type": "application/json", "Set-Cookie": null}"#;
+        let headers: Headers = serde_json::from_str(json).unwrap();
+        assert_eq!(headers.get("content-type"), Some("application/json"));
+        assert!(!headers.contains_key("set-cookie"));
+    }
+
+    #[test]
+    fn test_bifrost_error_code_is_retryable() {
+        assert!(BifrostErrorCode::RateLimited.is_retryable());
+        assert!(BifrostErrorCode::ProviderTimeout.is_retryable());
+        assert!(BifrostErrorCode::UpstreamIo.is_retryable());
+        assert!(!BifrostErrorCode::InvalidParams.is_retryable());
+        assert!(!BifrostErrorCode::SchemaValidation.is_retryable());
+        assert!(!BifrostErrorCode::ContextLengthExceeded.is_retryable());
+    }
+
+    #[test]
+    fn test_bifrost_error_with_error_code_defaults_allow_fallbacks() {
+        let error = BifrostError::new("rate limited").with_error_code(BifrostErrorCode::RateLimited);
+        assert_eq!(error.allow_fallbacks, Some(true));
+        assert!(error.is_retryable());
+
+        let error = BifrostError::new("bad params").with_error_code(BifrostErrorCode::InvalidParams);
+        assert_eq!(error.allow_fallbacks, Some(false));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn test_bifrost_error_with_error_code_respects_explicit_allow_fallbacks() {
+        // An explicit allow_fallbacks set before with_error_code must not be
+        // overridden by the code's default retryability.
+        let error = BifrostError {
+            allow_fallbacks: Some(false),
+            ..Default::default()
+        }
+        .with_error_code(BifrostErrorCode::RateLimited);
+        assert_eq!(error.allow_fallbacks, Some(false));
+    }
+
+    #[test]
+    fn test_bifrost_error_without_error_code_is_not_retryable() {
+        let error = BifrostError::new("plain error");
+        assert!(!error.is_retryable());
     }
 }
@@ -0,0 +1,83 @@
+//! Declarative content-type/empty-body validation for intercept hooks.
+//!
+//! Borrows the "no modeled input means content type must be absent and
+//! body empty" protocol check from server routing frameworks: rather than
+//! every plugin hand-rolling the same `GET /health` has no body" /
+//! `POST /v1/chat/completions` is `application/json`" checks,
+//! `RequestValidation` expresses the expectation declaratively and returns
+//! a structured rejection when the request doesn't match it.
+
+use crate::types::{BifrostError, HTTPRequest};
+
+/// What an `HTTPRequest`'s content type and body are expected to look
+/// like.
+enum ExpectedContentType {
+    /// No body, no `content-type` header (e.g. a `GET` with no payload).
+    None,
+    /// A non-empty body whose `content-type` matches case-insensitively
+    /// (ignoring any `;charset=...` parameter).
+    Exact(String),
+}
+
+/// A content-type/empty-body expectation for an intercept hook to check
+/// before running its own logic.
+pub struct RequestValidation {
+    expected: ExpectedContentType,
+}
+
+impl RequestValidation {
+    /// Expect no body and no `content-type` header.
+    pub fn none() -> Self {
+        Self {
+            expected: ExpectedContentType::None,
+        }
+    }
+
+    /// Expect a non-empty body with this `content-type` (case-insensitive).
+    pub fn content_type(content_type: impl Into<String>) -> Self {
+        Self {
+            expected: ExpectedContentType::Exact(content_type.into()),
+        }
+    }
+
+    /// Check `request` against the expectation, returning a structured
+    /// `BifrostError` (status + message) on mismatch.
+    pub fn validate(&self, request: &HTTPRequest) -> Result<(), BifrostError> {
+        let body_empty = request.body.as_bytes().is_empty();
+        let content_type = request.headers.get("content-type");
+
+        match &self.expected {
+            ExpectedContentType::None => {
+                if !body_empty {
+                    return Err(BifrostError::new("expected an empty body").with_status(400));
+                }
+                if content_type.is_some() {
+                    return Err(
+                        BifrostError::new("expected no content-type header").with_status(400)
+                    );
+                }
+            }
+            ExpectedContentType::Exact(expected) => {
+                if body_empty {
+                    return Err(BifrostError::new(&format!(
+                        "expected a non-empty \"{}\" body",
+                        expected
+                    ))
+                    .with_status(400));
+                }
+                let matches = content_type
+                    .map(|ct| ct.split(';').next().unwrap_or(ct).trim())
+                    .is_some_and(|ct| ct.eq_ignore_ascii_case(expected));
+                if !matches {
+                    return Err(BifrostError::new(&format!(
+                        "expected content-type \"{}\", got {:?}",
+                        expected, content_type
+                    ))
+                    .with_status(415));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}